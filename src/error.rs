@@ -0,0 +1,140 @@
+use std::fmt;
+use std::panic::Location;
+
+use crate::source_map::SourceMap;
+use crate::{ParseError, ParseErrorKind, PositionInfo, Token, TokenizeError, TokenizeErrorKind};
+
+pub struct Diagnostic<'a> {
+    source: &'a str,
+    position: PositionInfo,
+    span_len: usize,
+    message: String,
+    #[cfg(debug_assertions)]
+    trace: Option<&'static Location<'static>>,
+}
+
+impl<'a> Diagnostic<'a> {
+    fn new(source: &'a str, position: PositionInfo, span_len: usize, message: String) -> Self {
+        Self {
+            source,
+            position,
+            span_len: span_len.max(1),
+            message,
+            #[cfg(debug_assertions)]
+            trace: None,
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn with_trace(mut self, trace: &'static Location<'static>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+}
+
+impl<'a> fmt::Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.source[self.position.line_start_index..]
+            .lines()
+            .next()
+            .unwrap_or("");
+        let column = self.position.column as usize;
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "  --> {}:{}", self.position.line, self.position.column)?;
+        writeln!(f, "   |")?;
+        writeln!(f, "{:>3} | {}", self.position.line, line)?;
+        writeln!(f, "   | {}{}", " ".repeat(column), "^".repeat(self.span_len))?;
+
+        #[cfg(debug_assertions)]
+        if let Some(trace) = self.trace {
+            writeln!(f, "   = note: raised at {trace}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TokenizeError {
+    pub fn render<'a>(&self, map: &SourceMap<'a>) -> Diagnostic<'a> {
+        let position = TokenizeError::position(self, map);
+        let message = match self.kind() {
+            TokenizeErrorKind::ExpectedChar { expected, got } =>
+                format!("expected '{expected}', found '{got}'"),
+            TokenizeErrorKind::UnexpectedChar { got } =>
+                format!("unexpected character '{got}'"),
+            TokenizeErrorKind::UnexpectedEndOfInput =>
+                "unexpected end of input".to_string(),
+            TokenizeErrorKind::Custom { message } =>
+                (*message).to_string(),
+        };
+
+        let diagnostic = Diagnostic::new(map.source(), position, 1, message);
+        #[cfg(debug_assertions)]
+        let diagnostic = diagnostic.with_trace(self.trace());
+        diagnostic
+    }
+}
+
+impl<Kind: fmt::Debug> ParseError<Kind> {
+    pub fn render<'a>(&self, map: &SourceMap<'a>) -> Diagnostic<'a> {
+        let (index, span_len, message) = match self.kind() {
+            ParseErrorKind::UnexpectedToken(token) =>
+                (token.index as usize, token_char_len(map, token), format!("unexpected token {:?}", token.kind)),
+            ParseErrorKind::ExpectedToken { expected, got } =>
+                (got.index as usize, token_char_len(map, got), format!("expected one of {expected:?}, found {:?}", got.kind)),
+            ParseErrorKind::ExpectedString { expected, got, token } =>
+                (token.index as usize, token_char_len(map, token), format!("expected \"{expected}\", found \"{got}\"")),
+            ParseErrorKind::UnexpectedEndOfInput =>
+                (map.source().len(), 1, "unexpected end of input".to_string()),
+        };
+
+        let position = map.position(index);
+        let diagnostic = Diagnostic::new(map.source(), position, span_len, message);
+        #[cfg(debug_assertions)]
+        let diagnostic = diagnostic.with_trace(self.trace());
+        diagnostic
+    }
+}
+
+// The caret underline is measured in `char`s (to match `SourceMap::position`'s
+// column), while `Token::range` is a byte range, so multi-byte UTF-8 tokens
+// need their width recomputed from the source rather than reusing the byte length.
+fn token_char_len<Kind>(map: &SourceMap, token: &Token<Kind>) -> usize {
+    map.source()[Token::range(token)].chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Kind {
+        Word,
+    }
+
+    fn parse_error(kind: ParseErrorKind<Kind>) -> ParseError<Kind> {
+        ParseError {
+            kind,
+            #[cfg(debug_assertions)]
+            source: Location::caller(),
+        }
+    }
+
+    #[test]
+    fn caret_width_matches_chars_not_bytes_for_multi_byte_tokens() {
+        let source = "héllo world";
+        let map = SourceMap::new(source);
+        // "héllo" is 6 bytes (é is 2 bytes) but 5 chars.
+        let token = Token { kind: Kind::Word, index: 0, len: 6 };
+        let error = parse_error(ParseErrorKind::UnexpectedToken(token));
+
+        let rendered = error.render(&map).to_string();
+        let underline = rendered.lines()
+            .find(|line| line.contains('^'))
+            .expect("rendered diagnostic has an underline");
+        let caret_count = underline.chars().filter(|&c| c == '^').count();
+
+        assert_eq!(caret_count, "héllo".chars().count());
+    }
+}
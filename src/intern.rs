@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Atom(u32);
+
+#[derive(Default)]
+pub struct Interner {
+    strings: Vec<Box<str>>,
+    lookup: HashMap<&'static str, Atom>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, content: &str) -> Atom {
+        if let Some(atom) = self.lookup.get(content) {
+            return *atom;
+        }
+        let atom = Atom(u32::try_from(self.strings.len()).expect("too many interned strings!"));
+        let boxed: Box<str> = content.into();
+        // SAFETY: moving a `Box<str>` into `self.strings` relocates the fat
+        // pointer, not the heap allocation it points to, and `Interner` only
+        // ever appends entries (never removes or mutates one), so a key
+        // borrowed from `boxed` here stays valid for as long as `self` does.
+        // This avoids allocating the same string content twice (once for
+        // `strings`, once more as an owned `HashMap` key).
+        let key: &'static str = unsafe { std::mem::transmute::<&str, &'static str>(&boxed) };
+        self.strings.push(boxed);
+        self.lookup.insert(key, atom);
+        atom
+    }
+
+    pub fn resolve(&self, atom: Atom) -> &str {
+        &self.strings[atom.0 as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_the_same_content_twice_returns_the_same_atom() {
+        let mut interner = Interner::new();
+        let a = interner.intern("foo");
+        let b = interner.intern("foo");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn distinct_content_gets_distinct_atoms() {
+        let mut interner = Interner::new();
+        let a = interner.intern("foo");
+        let b = interner.intern("bar");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn resolve_survives_reallocation_of_the_backing_storage() {
+        let mut interner = Interner::new();
+        let first = interner.intern("atom-0");
+
+        // Force `strings` to grow and reallocate several times; the `Box<str>`
+        // heap buffers it holds must not move even though the `Vec` does.
+        for i in 1..1000 {
+            interner.intern(&format!("atom-{i}"));
+        }
+
+        assert_eq!(interner.resolve(first), "atom-0");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{SimpleTokenizer, Tokens, TokenizeError};
+
+pub enum Pattern {
+    Literal(&'static str),
+    While(fn(char) -> bool),
+    Seq(&'static [Pattern]),
+    Opt(&'static Pattern),
+    Repeat(&'static Pattern),
+}
+
+impl Pattern {
+    // `None` means "didn't match"; `Some(0)` means "matched, zero chars
+    // consumed" (e.g. an absent `Opt`). These are deliberately distinct so a
+    // zero-width match inside a `Seq` doesn't get mistaken for a failure.
+    fn match_len(&self, input: &str) -> Option<usize> {
+        match self {
+            Pattern::Literal(lit) => input.starts_with(lit).then_some(lit.len()),
+            Pattern::While(predicate) => {
+                let len: usize = input.chars()
+                    .take_while(|c| predicate(*c))
+                    .map(char::len_utf8)
+                    .sum();
+                (len > 0).then_some(len)
+            }
+            Pattern::Seq(patterns) => {
+                let mut total = 0;
+                for pattern in *patterns {
+                    total += pattern.match_len(&input[total..])?;
+                }
+                Some(total)
+            }
+            Pattern::Opt(pattern) => Some(pattern.match_len(input).unwrap_or(0)),
+            Pattern::Repeat(pattern) => {
+                let mut total = 0;
+                while let Some(len) = pattern.match_len(&input[total..]).filter(|&len| len > 0) {
+                    total += len;
+                }
+                (total > 0).then_some(total)
+            }
+        }
+    }
+}
+
+pub fn tokenize_with<Kind: Copy>(
+    source: &str,
+    rules: &[(Kind, bool, Pattern)],
+) -> Result<Tokens<Kind>, TokenizeError> {
+    let mut tokenizer = SimpleTokenizer::from(source);
+    let mut tokens = Vec::new();
+    let end = u32::try_from(source.len()).expect("input too big!");
+
+    while tokenizer.has_more_chars() {
+        tokenizer.begin_token();
+        let start = tokenizer.get_index();
+        let rest = tokenizer.slice(start..end);
+
+        let mut best: Option<(Kind, bool, usize)> = None;
+        for (kind, skip, pattern) in rules {
+            // A zero-length match can't make progress, so it can never win;
+            // otherwise the tokenizer would spin forever on the same index.
+            let len = match pattern.match_len(rest) {
+                Some(len) if len > 0 => len,
+                _ => continue,
+            };
+            match best {
+                Some((_, _, best_len)) if best_len >= len => {}
+                _ => best = Some((*kind, *skip, len)),
+            }
+        }
+
+        let Some((kind, skip, len)) = best else {
+            let got = tokenizer.peek()?;
+            return Err(tokenizer.unexpected(got));
+        };
+
+        tokenizer.set_index(start + u32::try_from(len).expect("input too big!"));
+        let token = tokenizer.end_token(kind);
+        if !skip {
+            tokens.push(token);
+        }
+    }
+
+    Ok(tokens.into_iter().collect())
+}
+
+#[macro_export]
+macro_rules! tokenizer {
+    (
+        $vis:vis enum $Kind:ident {
+            $( $(#[$skip_attr:ident])? $variant:ident = $pattern:expr ),* $(,)?
+        }
+    ) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis enum $Kind {
+            $($variant),*
+        }
+
+        impl $Kind {
+            pub fn tokenize(source: &str) -> ::std::result::Result<$crate::Tokens<$Kind>, $crate::TokenizeError> {
+                let rules: &[($Kind, bool, $crate::lexer::Pattern)] = &[
+                    $(
+                        ($Kind::$variant, $crate::tokenizer!(@is_skip $($skip_attr)?), $pattern)
+                    ),*
+                ];
+                $crate::lexer::tokenize_with(source, rules)
+            }
+        }
+    };
+    (@is_skip skip) => { true };
+    (@is_skip) => { false };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Eq,
+        Assign,
+        Word,
+        Number,
+    }
+
+    fn is_digit(c: char) -> bool {
+        c.is_ascii_digit()
+    }
+
+    #[test]
+    fn longest_match_wins_over_a_shorter_prefix_match() {
+        let rules = &[
+            (Kind::Assign, false, Pattern::Literal("=")),
+            (Kind::Eq, false, Pattern::Literal("==")),
+        ];
+        let tokens = tokenize_with("==", rules).unwrap();
+        assert_eq!(tokens.inner.len(), 1);
+        assert_eq!(tokens.inner[0].kind, Kind::Eq);
+        assert_eq!(tokens.inner[0].len, 2);
+    }
+
+    #[test]
+    fn first_declared_rule_wins_on_a_length_tie() {
+        let by_literal_first = &[
+            (Kind::Assign, false, Pattern::Literal("if")),
+            (Kind::Word, false, Pattern::While(char::is_alphabetic)),
+        ];
+        let tokens = tokenize_with("if", by_literal_first).unwrap();
+        assert_eq!(tokens.inner[0].kind, Kind::Assign);
+
+        let by_while_first = &[
+            (Kind::Word, false, Pattern::While(char::is_alphabetic)),
+            (Kind::Assign, false, Pattern::Literal("if")),
+        ];
+        let tokens = tokenize_with("if", by_while_first).unwrap();
+        assert_eq!(tokens.inner[0].kind, Kind::Word);
+    }
+
+    #[test]
+    fn seq_opt_and_repeat_compose_a_number_literal_pattern() {
+        // sign? digit+ ('.' digit+)?
+        const SIGN: Pattern = Pattern::Opt(&Pattern::Literal("-"));
+        const DIGITS: Pattern = Pattern::Repeat(&Pattern::While(is_digit));
+        const FRACTION: Pattern = Pattern::Opt(&Pattern::Seq(&[Pattern::Literal("."), DIGITS]));
+        const NUMBER: Pattern = Pattern::Seq(&[SIGN, DIGITS, FRACTION]);
+
+        assert_eq!(NUMBER.match_len("123"), Some(3));
+        assert_eq!(NUMBER.match_len("-123"), Some(4));
+        assert_eq!(NUMBER.match_len("-12.34"), Some(6));
+        assert_eq!(NUMBER.match_len("12.34rest"), Some(5));
+        assert_eq!(NUMBER.match_len("abc"), None);
+
+        let rules = &[(Kind::Number, false, NUMBER)];
+        let tokens = tokenize_with("-12.5", rules).unwrap();
+        assert_eq!(tokens.inner.len(), 1);
+        assert_eq!(tokens.inner[0].kind, Kind::Number);
+        assert_eq!(tokens.inner[0].len, 5);
+    }
+}
@@ -1,9 +1,17 @@
 #![allow(dead_code)]
 
+use std::cell::RefCell;
 use std::ops::Range;
 use std::panic::Location;
 
+use crate::intern::{Atom, Interner};
+use crate::source_map::SourceMap;
+
 pub mod error;
+pub mod intern;
+pub mod lexer;
+pub mod source_map;
+pub mod token_source;
 
 #[derive(Debug, Clone)]
 pub struct Token<Kind> {
@@ -58,36 +66,11 @@ pub struct PositionInfo {
 }
 
 impl TokenizeError {
-    pub fn position(this: &Self, source: &str) -> PositionInfo {
+    pub fn position(this: &Self, map: &SourceMap) -> PositionInfo {
         let index = this.index.try_into().expect("input too big!");
-        let mut line = 1;
-        let mut line_start_index = 0;
-        let mut column = 0;
-        let mut str_index = 0;
-
-        for c in source.chars() {
-            if c == '\n' {
-                line += 1;
-                line_start_index = str_index;
-                column = 0;
-            } else {
-                column += 1;
-            }
-            str_index += c.len_utf8();
-            if str_index >= index {
-                break;
-            }
-        }
-
-        PositionInfo {
-            line,
-            column,
-            line_start_index,
-            index,
-        }
+        map.position(index)
     }
 
-
     pub fn index(&self) -> u32 {
         self.index
     }
@@ -138,10 +121,7 @@ impl<'a> SimpleTokenizer<'a> {
                 #[cfg(debug_assertions)]
                 source: Location::caller(),
             })
-            .map(|char| {
-                self.index += char.len_utf8();
-                char
-            })
+            .inspect(|char| self.index += char.len_utf8())
     }
 
     pub fn content<Kind>(&self, tok: &Token<Kind>) -> Option<&str> {
@@ -247,7 +227,7 @@ impl<'a> From<&'a str> for SimpleTokenizer<'a> {
 pub enum ParseErrorKind<Kind> {
     UnexpectedToken(Token<Kind>),
     ExpectedToken {
-        expected: Kind,
+        expected: Vec<Kind>,
         got: Token<Kind>,
     },
     ExpectedString {
@@ -265,14 +245,65 @@ pub struct ParseError<Kind> {
     source: &'static Location<'static>,
 }
 
+impl<Kind> ParseError<Kind> {
+    pub fn kind(&self) -> &ParseErrorKind<Kind> {
+        &self.kind
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn trace(&self) -> &'static Location<'static> {
+        self.source
+    }
+
+    pub fn expected(mut self, kinds: &[Kind]) -> Self
+    where
+        Kind: Ord + Clone,
+    {
+        if let ParseErrorKind::ExpectedToken { expected, .. } = &mut self.kind {
+            expected.extend(kinds.iter().cloned());
+            expected.sort();
+            expected.dedup();
+        }
+        self
+    }
+
+    fn merge(self, other: Self) -> Self
+    where
+        Kind: Ord + Clone,
+    {
+        match (self.kind, other.kind) {
+            (
+                ParseErrorKind::ExpectedToken { mut expected, got },
+                ParseErrorKind::ExpectedToken { expected: other_expected, .. },
+            ) => {
+                expected.extend(other_expected);
+                expected.sort();
+                expected.dedup();
+                ParseError {
+                    kind: ParseErrorKind::ExpectedToken { expected, got },
+                    #[cfg(debug_assertions)]
+                    source: self.source,
+                }
+            }
+            (kind, _) => ParseError {
+                kind,
+                #[cfg(debug_assertions)]
+                source: self.source,
+            },
+        }
+    }
+}
+
 pub struct Tokens<Kind> {
     inner: Vec<Token<Kind>>,
+    interner: RefCell<Interner>,
 }
 
 impl<Kind> FromIterator<Token<Kind>> for Tokens<Kind> {
     fn from_iter<T: IntoIterator<Item=Token<Kind>>>(iter: T) -> Self {
         Self {
             inner: iter.into_iter().collect(),
+            interner: RefCell::new(Interner::new()),
         }
     }
 }
@@ -283,6 +314,8 @@ pub struct TokenView<'a, Kind> {
     index: usize,
 }
 
+type AltParser<'p, 'a, Kind, T> = &'p dyn Fn(&mut TokenView<'a, Kind>) -> Result<T, ParseError<Kind>>;
+
 impl<'a, Kind> TokenView<'a, Kind> {
     pub fn new(source: &'a str, tokens: &'a Tokens<Kind>) -> Self {
         Self {
@@ -329,17 +362,19 @@ impl<'a, Kind> TokenView<'a, Kind> {
 
     #[track_caller]
     pub fn expect(&mut self, kind: Kind) -> Result<&'a Token<Kind>, ParseError<Kind>> where Kind: Eq + Clone {
-        let token = self.take()?;
-        match token {
-            token if token.kind == kind => Ok(token),
-            token => Err(ParseError {
+        let token = self.peek()?;
+        if token.kind == kind {
+            self.index += 1;
+            Ok(token)
+        } else {
+            Err(ParseError {
                 kind: ParseErrorKind::ExpectedToken {
-                    expected: kind,
+                    expected: vec![kind],
                     got: token.clone(),
                 },
                 #[cfg(debug_assertions)]
                 source: Location::caller(),
-            }),
+            })
         }
     }
 
@@ -355,6 +390,21 @@ impl<'a, Kind> TokenView<'a, Kind> {
         self.source.get(start..end)
     }
 
+    pub fn content_atom(&self, token: &'a Token<Kind>) -> Atom {
+        let content = self.content(token).expect("token content not in source");
+        self.tokens.interner.borrow_mut().intern(content)
+    }
+
+    pub fn resolve(&self, atom: Atom) -> &'a str {
+        let interner = self.tokens.interner.borrow();
+        let resolved: &str = interner.resolve(atom);
+        // SAFETY: `Interner` only ever appends `Box<str>` entries and never
+        // removes or mutates them, so a resolved string's backing allocation
+        // stays valid for as long as the `Tokens` ('a) that owns the
+        // interner, independent of this `Ref`'s borrow scope.
+        unsafe { std::mem::transmute::<&str, &'a str>(resolved) }
+    }
+
     #[track_caller]
     pub fn content_matches(&self, input: impl AsRef<str>) -> Result<&'a str, ParseError<Kind>> where Kind: Clone {
         let input = String::from(input.as_ref());
@@ -421,10 +471,275 @@ impl<'a, Kind> TokenView<'a, Kind> {
             source: Location::caller(),
         }
     }
+
+    pub fn or_else<T>(
+        &mut self,
+        first: impl FnOnce(&mut Self) -> Result<T, ParseError<Kind>>,
+        second: impl FnOnce(&mut Self) -> Result<T, ParseError<Kind>>,
+    ) -> Result<T, ParseError<Kind>>
+    where
+        Kind: Ord + Clone,
+    {
+        let start = self.index;
+        match first(self) {
+            Ok(value) => Ok(value),
+            Err(err) if self.index == start => {
+                self.index = start;
+                match second(self) {
+                    Ok(value) => Ok(value),
+                    Err(err2) if self.index == start => Err(err.merge(err2)),
+                    Err(err2) => Err(err2),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn alt<T>(
+        &mut self,
+        parsers: &[AltParser<'_, 'a, Kind, T>],
+    ) -> Result<T, ParseError<Kind>>
+    where
+        Kind: Ord + Clone,
+    {
+        let start = self.index;
+        let mut merged: Option<ParseError<Kind>> = None;
+        for parser in parsers {
+            self.index = start;
+            match parser(self) {
+                Ok(value) => return Ok(value),
+                Err(err) if self.index == start => {
+                    merged = Some(match merged {
+                        Some(prev) => prev.merge(err),
+                        None => err,
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(merged.unwrap_or_else(|| self.unexpected_end()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+impl<'a, Kind> TokenView<'a, Kind> {
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.index)
+    }
+
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.index = checkpoint.0;
+    }
+
+    pub fn try_parse<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError<Kind>>,
+    ) -> Result<T, ParseError<Kind>> {
+        let checkpoint = self.checkpoint();
+        match parse(self) {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                self.rollback(checkpoint);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn optional<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError<Kind>>,
+    ) -> Option<T> {
+        self.try_parse(parse).ok()
+    }
+
+    pub fn many0<T>(
+        &mut self,
+        mut parse: impl FnMut(&mut Self) -> Result<T, ParseError<Kind>>,
+    ) -> Vec<T> {
+        let mut values = Vec::new();
+        loop {
+            let start = self.index;
+            match self.optional(&mut parse) {
+                Some(value) => {
+                    values.push(value);
+                    if self.index == start {
+                        // `parse` succeeded without consuming a token; stop here
+                        // instead of looping forever on a zero-width match.
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        values
+    }
+
+    pub fn many1<T>(
+        &mut self,
+        mut parse: impl FnMut(&mut Self) -> Result<T, ParseError<Kind>>,
+    ) -> Result<Vec<T>, ParseError<Kind>> {
+        let first = parse(self)?;
+        let mut values = vec![first];
+        values.extend(self.many0(parse));
+        Ok(values)
+    }
+
+    pub fn separated<T, U>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError<Kind>>,
+        mut separator: impl FnMut(&mut Self) -> Result<U, ParseError<Kind>>,
+    ) -> Result<Vec<T>, ParseError<Kind>> {
+        let mut values = vec![item(self)?];
+        loop {
+            let start = self.index;
+            match self.optional(|view| {
+                separator(view)?;
+                item(view)
+            }) {
+                Some(value) => {
+                    values.push(value);
+                    if self.index == start {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(values)
+    }
 }
 
 #[derive(Debug)]
 pub enum Error<Kind> {
     Tokenizer(TokenizeError),
     Parser(ParseError<Kind>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Kind {
+        A,
+        B,
+        C,
+    }
+
+    fn token(kind: Kind, index: u32) -> Token<Kind> {
+        Token { kind, index, len: 1 }
+    }
+
+    fn tokens(kinds: &[Kind]) -> Tokens<Kind> {
+        kinds.iter().enumerate()
+            .map(|(i, &kind)| token(kind, i as u32))
+            .collect()
+    }
+
+    #[test]
+    fn token_view_expect_does_not_consume_on_mismatch() {
+        let tokens = tokens(&[Kind::A]);
+        let mut view = TokenView::new("x", &tokens);
+        assert!(view.expect(Kind::B).is_err());
+        assert_eq!(view.index, 0);
+        assert!(view.expect(Kind::A).is_ok());
+        assert_eq!(view.index, 1);
+    }
+
+    #[test]
+    fn checkpoint_rolls_back_to_the_saved_index() {
+        let tokens = tokens(&[Kind::A, Kind::B]);
+        let mut view = TokenView::new("xy", &tokens);
+        let checkpoint = view.checkpoint();
+        view.take().unwrap();
+        assert_eq!(view.index, 1);
+        view.rollback(checkpoint);
+        assert_eq!(view.index, 0);
+    }
+
+    #[test]
+    fn optional_rolls_back_on_failure_and_keeps_progress_on_success() {
+        let tokens = tokens(&[Kind::A]);
+        let mut view = TokenView::new("x", &tokens);
+        assert!(view.optional(|view| view.expect(Kind::B)).is_none());
+        assert_eq!(view.index, 0);
+        assert!(view.optional(|view| view.expect(Kind::A)).is_some());
+        assert_eq!(view.index, 1);
+    }
+
+    #[test]
+    fn many0_collects_until_the_parser_fails() {
+        let tokens = tokens(&[Kind::A, Kind::A, Kind::B]);
+        let mut view = TokenView::new("xxy", &tokens);
+        let values = view.many0(|view| view.expect(Kind::A));
+        assert_eq!(values.len(), 2);
+        assert_eq!(view.index, 2);
+    }
+
+    #[test]
+    fn many0_stops_instead_of_looping_forever_on_a_zero_width_match() {
+        let tokens = tokens(&[Kind::A]);
+        let mut view = TokenView::new("x", &tokens);
+        let values = view.many0(|_| Ok::<(), ParseError<Kind>>(()));
+        assert_eq!(values.len(), 1);
+        assert_eq!(view.index, 0);
+    }
+
+    #[test]
+    fn many1_requires_at_least_one_match() {
+        let empty_match = tokens(&[Kind::B]);
+        let mut view = TokenView::new("y", &empty_match);
+        assert!(view.many1(|view| view.expect(Kind::A)).is_err());
+        assert_eq!(view.index, 0);
+
+        let two_matches = tokens(&[Kind::A, Kind::A, Kind::B]);
+        let mut view = TokenView::new("xxy", &two_matches);
+        let values = view.many1(|view| view.expect(Kind::A)).unwrap();
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn separated_collects_items_between_separators() {
+        let tokens = tokens(&[Kind::A, Kind::C, Kind::A, Kind::C, Kind::A]);
+        let mut view = TokenView::new("xyxyx", &tokens);
+        let values = view.separated(
+            |view| view.expect(Kind::A),
+            |view| view.expect(Kind::C),
+        ).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(view.index, 5);
+    }
+
+    #[test]
+    fn alt_merges_expected_sets_only_at_same_index() {
+        let tokens = tokens(&[Kind::C, Kind::A]);
+        let mut view = TokenView::new("xyz", &tokens);
+
+        let err = view.alt(&[
+            &|view: &mut TokenView<Kind>| view.expect(Kind::A).map(|_| ()),
+            &|view: &mut TokenView<Kind>| view.expect(Kind::B).map(|_| ()),
+        ]).unwrap_err();
+        match err.kind() {
+            ParseErrorKind::ExpectedToken { expected, .. } => {
+                assert_eq!(expected, &[Kind::A, Kind::B]);
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+
+        let err = view.alt(&[
+            &|view: &mut TokenView<Kind>| view.expect(Kind::A).map(|_| ()),
+            &|view: &mut TokenView<Kind>| {
+                view.expect(Kind::C)?;
+                view.expect(Kind::B).map(|_| ())
+            },
+        ]).unwrap_err();
+        match err.kind() {
+            ParseErrorKind::ExpectedToken { expected, .. } => {
+                assert_eq!(expected, &[Kind::B]);
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use crate::PositionInfo;
+
+pub struct SourceMap<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source.char_indices()
+                .filter(|&(_, c)| c == '\n')
+                .map(|(i, c)| i + c.len_utf8())
+        );
+        Self { source, line_starts }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn position(&self, index: usize) -> PositionInfo {
+        let line_idx = self.line_starts.partition_point(|&start| start <= index) - 1;
+        let line_start_index = self.line_starts[line_idx];
+        let line = u32::try_from(line_idx + 1).expect("too many lines!");
+
+        let column_end = index.min(self.source.len());
+        let column = u32::try_from(self.source[line_start_index..column_end].chars().count())
+            .expect("line too long!");
+
+        PositionInfo {
+            line,
+            column,
+            line_start_index,
+            index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_on_newline_belongs_to_terminated_line() {
+        let map = SourceMap::new("abc\ndef");
+        let newline_index = "abc".len();
+        let position = map.position(newline_index);
+        assert_eq!(position.line, 1);
+        assert_eq!(position.column, 3);
+    }
+
+    #[test]
+    fn index_after_newline_belongs_to_next_line() {
+        let map = SourceMap::new("abc\ndef");
+        let position = map.position("abc\n".len());
+        assert_eq!(position.line, 2);
+        assert_eq!(position.column, 0);
+    }
+}
@@ -0,0 +1,214 @@
+use std::panic::Location;
+
+use crate::{Error, ParseError, ParseErrorKind, SimpleTokenizer, Token, Tokens, TokenizeError};
+
+pub trait TokenSource<Kind> {
+    fn token_at(&mut self, index: usize) -> Result<Option<Token<Kind>>, TokenizeError>;
+}
+
+impl<Kind: Clone> TokenSource<Kind> for Tokens<Kind> {
+    fn token_at(&mut self, index: usize) -> Result<Option<Token<Kind>>, TokenizeError> {
+        Ok(self.inner.get(index).cloned())
+    }
+}
+
+pub struct LazyTokens<'a, Kind> {
+    tokenizer: SimpleTokenizer<'a>,
+    next: fn(&mut SimpleTokenizer<'a>) -> Result<Option<Token<Kind>>, TokenizeError>,
+    buffer: Vec<Token<Kind>>,
+    exhausted: bool,
+}
+
+impl<'a, Kind> LazyTokens<'a, Kind> {
+    pub fn new(
+        source: &'a str,
+        next: fn(&mut SimpleTokenizer<'a>) -> Result<Option<Token<Kind>>, TokenizeError>,
+    ) -> Self {
+        Self {
+            tokenizer: SimpleTokenizer::from(source),
+            next,
+            buffer: Vec::new(),
+            exhausted: false,
+        }
+    }
+
+    fn fill_to(&mut self, index: usize) -> Result<(), TokenizeError> {
+        while !self.exhausted && self.buffer.len() <= index {
+            match (self.next)(&mut self.tokenizer)? {
+                Some(token) => self.buffer.push(token),
+                None => self.exhausted = true,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, Kind: Clone> TokenSource<Kind> for LazyTokens<'a, Kind> {
+    fn token_at(&mut self, index: usize) -> Result<Option<Token<Kind>>, TokenizeError> {
+        self.fill_to(index)?;
+        Ok(self.buffer.get(index).cloned())
+    }
+}
+
+pub struct StreamingView<'a, Kind, S> {
+    source: &'a str,
+    tokens: S,
+    index: usize,
+    _marker: std::marker::PhantomData<Kind>,
+}
+
+impl<'a, Kind, S: TokenSource<Kind>> StreamingView<'a, Kind, S> {
+    pub fn new(source: &'a str, tokens: S) -> Self {
+        Self {
+            source,
+            tokens,
+            index: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn token_at(&mut self, index: usize) -> Result<Option<Token<Kind>>, Error<Kind>> {
+        self.tokens.token_at(index).map_err(Error::Tokenizer)
+    }
+
+    #[track_caller]
+    pub fn peek(&mut self) -> Result<Token<Kind>, Error<Kind>> {
+        self.token_at(self.index)?.ok_or_else(|| Error::Parser(self.unexpected_end()))
+    }
+
+    pub fn peekn(&mut self, off: isize) -> Result<Option<Token<Kind>>, Error<Kind>> {
+        let pos = isize::try_from(self.index).expect("too many tokens!") + off;
+        match usize::try_from(pos) {
+            Ok(pos) => self.token_at(pos),
+            Err(_) => Ok(None),
+        }
+    }
+
+    #[track_caller]
+    pub fn take(&mut self) -> Result<Token<Kind>, Error<Kind>> {
+        let token = self.peek()?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    #[track_caller]
+    pub fn expect(&mut self, kind: Kind) -> Result<Token<Kind>, Error<Kind>>
+    where
+        Kind: Eq,
+    {
+        let token = self.peek()?;
+        if token.kind == kind {
+            self.index += 1;
+            Ok(token)
+        } else {
+            Err(Error::Parser(ParseError {
+                kind: ParseErrorKind::ExpectedToken {
+                    expected: vec![kind],
+                    got: token,
+                },
+                #[cfg(debug_assertions)]
+                source: Location::caller(),
+            }))
+        }
+    }
+
+    pub fn content(&self, token: &Token<Kind>) -> Option<&'a str> {
+        let start = usize::try_from(token.index).expect("token index too big");
+        let end = usize::try_from(token.index + token.len).expect("token end too long");
+        self.source.get(start..end)
+    }
+
+    pub fn matches(&mut self, kind: Kind) -> bool
+    where
+        Kind: Eq,
+    {
+        matches!(self.peek(), Ok(token) if token.kind == kind)
+    }
+
+    pub fn match_and_take(&mut self, kind: Kind) -> bool
+    where
+        Kind: Eq,
+    {
+        match self.peek() {
+            Ok(token) if token.kind == kind => {
+                self.index += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn has_more_tokens(&mut self) -> bool {
+        matches!(self.token_at(self.index), Ok(Some(_)))
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn set_position(&mut self, idx: usize) -> bool {
+        if idx == 0 {
+            self.index = idx;
+            return true;
+        }
+        match self.token_at(idx - 1) {
+            Ok(Some(_)) => {
+                self.index = idx;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[track_caller]
+    fn unexpected_end(&self) -> ParseError<Kind> {
+        ParseError {
+            kind: ParseErrorKind::UnexpectedEndOfInput,
+            #[cfg(debug_assertions)]
+            source: Location::caller(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        A,
+        B,
+    }
+
+    fn tokens(kinds: &[Kind]) -> Tokens<Kind> {
+        kinds.iter().enumerate()
+            .map(|(i, &kind)| Token { kind, index: i as u32, len: 1 })
+            .collect()
+    }
+
+    #[test]
+    fn streaming_view_expect_does_not_consume_on_mismatch() {
+        let tokens = tokens(&[Kind::A]);
+        let mut view = StreamingView::new("x", tokens);
+        assert!(view.expect(Kind::B).is_err());
+        assert_eq!(view.index(), 0);
+        assert!(view.expect(Kind::A).is_ok());
+        assert_eq!(view.index(), 1);
+    }
+
+    #[test]
+    fn set_position_rejects_an_out_of_range_index() {
+        let tokens = tokens(&[Kind::A, Kind::B]);
+        let mut view = StreamingView::new("xy", tokens);
+
+        assert!(view.set_position(0));
+        assert_eq!(view.index(), 0);
+
+        assert!(view.set_position(2));
+        assert_eq!(view.index(), 2);
+
+        assert!(!view.set_position(3));
+        // a rejected `set_position` must leave the cursor where it was.
+        assert_eq!(view.index(), 2);
+    }
+}